@@ -1,15 +1,15 @@
+mod simd;
+mod station_table;
+
 use bumpalo::Bump;
-use std::collections::HashMap;
+use memmap2::Mmap;
+use station_table::{CityData, StationTable};
 use std::fs::{self, File};
-use std::io::BufRead;
 use std::path::Path;
 use std::time::Instant;
 
-const INPUT_FILE_PATH: &'static str = "/dev/shm/measurements.txt";
-const OUTPUT_FILE_PATH: &'static str = "summary.txt";
-// Size of the buffer that will hold the binary data.
-// Here I chose a 100MiB buffer.
-const FILE_BUFF_SIZE: usize = 1024 * 1024 * 100;
+const INPUT_FILE_PATH: &str = "/dev/shm/measurements.txt";
+const OUTPUT_FILE_PATH: &str = "summary.txt";
 const CITY_NAME_BUFF_SIZE: usize = 1024 * 1024 * 100;
 
 fn main() {
@@ -21,58 +21,20 @@ fn main() {
         panic!("The provided file does not exist.");
     }
 
-    // open the file
-    let file_handle = File::open(path).expect("Could not open file.");
-
-    // We create a buffer reader.
-    // This is a performance optimization, as it allows us to read the file in chunks,
-    // instead of doing a syscall for each line, without compromising on code readability.
-    // Technically this isn't needed, because our file is on `/dev/shm`, but I let it here
-    // to make sure the code still works properly if the file is on a regular disk.
-    let mut reader = std::io::BufReader::with_capacity(FILE_BUFF_SIZE, file_handle);
+    // Memory-map the file instead of reading it into an owned buffer. The kernel pages the
+    // data in on demand, and every worker thread below just gets a sub-slice of the mapping
+    // to parse directly, with no per-line copying.
+    let file_contents = load_file_bytes(path);
+    let file_contents: &[u8] = &file_contents;
 
-    // create our data holding structures
-    let city_name_buffer = &mut Bump::with_capacity(CITY_NAME_BUFF_SIZE);
-    let mut data_summary: HashMap<&str, CityData> = HashMap::new();
-    let mut line_counter: u64 = 0;
-    let mut line_buffer = String::with_capacity(100); // a single allocation for the whole program
+    let num_threads = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
 
-    // iterate over the lines
     let start_time = Instant::now();
-    loop {
-        // clear the line buffer
-        line_buffer.clear();
-
-        // read a line into the buffer
-        let bytes_read = reader
-            .read_line(&mut line_buffer)
-            .expect("Could not read line.");
-
-        // exit the loop if we reached the end of the file
-        if bytes_read == 0 {
-            break;
-        }
-
-        // process the line & append to the data summary
-        process_line(city_name_buffer, &mut data_summary, &line_buffer);
-
-        // increment the line counter
-        line_counter += 1;
-    }
+    let (summary_string, line_counter) = summarize(file_contents, num_threads);
     let duration = start_time.elapsed();
 
-    // save the summary to a String
-    let mut summary_string = String::with_capacity(1024 * 1024 * 1024); // a single allocation for the whole program
-    summary_string.push('{');
-    data_summary.values().for_each(|city_data| {
-        city_data.summary(&mut summary_string);
-        summary_string.push(',');
-        summary_string.push(' ');
-    });
-    summary_string.pop(); // remove the last space
-    summary_string.pop(); // remove the last comma
-    summary_string.push('}');
-
     // write the summary to a file
     fs::write(OUTPUT_FILE_PATH, summary_string).expect("Could not write summary to file.");
 
@@ -84,32 +46,194 @@ fn main() {
     );
 }
 
-/// Data structure to hold a single city data
-/// Instead of using floats, we use integers to represent the temperature,
-/// as we know that the temperature is given in 0.1° increments
-struct CityData<'a> {
-    city_name: &'a str,
-    minimum_temperature: i64,
-    maximum_temperature: i64,
-    temperatures_sum: i64,
-    data_points: i64,
+/// A `Bump` arena that is only ever touched by the single worker thread it is handed to.
+///
+/// Safety: `Bump` holds interior-mutable state (`Cell`s) and so isn't `Sync`, which means a
+/// plain `&Bump` can't cross the `thread::scope::spawn` boundary. Each arena here is used by
+/// exactly one thread for its entire lifetime - no two threads ever read or write the same
+/// arena concurrently - so sharing `&ThreadLocalArena` across the scope is sound even though
+/// sharing `&Bump` directly is not.
+struct ThreadLocalArena(Bump);
+
+unsafe impl Sync for ThreadLocalArena {}
+
+impl std::ops::Deref for ThreadLocalArena {
+    type Target = Bump;
+
+    fn deref(&self) -> &Bump {
+        &self.0
+    }
 }
 
-impl<'a> CityData<'a> {
-    /// Implement a summary function for the CityData struct
-    /// We don't want heap allocation, so we use a mutable string reference on which we append the summary.
-    fn summary(&self, summary_string: &mut String) {
-        summary_string.push_str(self.city_name);
-        summary_string.push('=');
-        int_to_temperature::<10>(summary_string, self.minimum_temperature);
-        summary_string.push('/');
-        int_to_temperature::<10>(
-            summary_string,
-            // there probably are some rounding errors here, but it's beside the point of the challenge
-            self.temperatures_sum / self.data_points,
-        );
-        summary_string.push('/');
-        int_to_temperature::<10>(summary_string, self.maximum_temperature);
+/// Shard `file_contents` across `num_threads` worker threads, parse and summarize each
+/// shard, merge the results, and render the sorted `{Station=min/mean/max, ...}` string.
+/// Returns the rendered summary together with the total number of lines processed.
+fn summarize(file_contents: &[u8], num_threads: usize) -> (String, u64) {
+    // Shard the file into roughly one chunk per available core, snapping each boundary
+    // forward to the next newline so a chunk never starts or ends in the middle of a line.
+    let chunks = split_into_chunks(file_contents, num_threads);
+
+    // Each worker gets its own arena so the threads never contend over allocation, and the
+    // arenas are kept alive here (outside the scope) so the city names each thread hands
+    // back remain valid once we start merging.
+    let arenas: Vec<ThreadLocalArena> = chunks
+        .iter()
+        .map(|_| ThreadLocalArena(Bump::with_capacity(CITY_NAME_BUFF_SIZE / num_threads.max(1))))
+        .collect();
+
+    let chunk_results: Vec<(StationTable, u64)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = chunks
+            .iter()
+            .zip(arenas.iter())
+            .map(|(chunk, arena)| scope.spawn(move || process_chunk(arena, chunk)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("A worker thread panicked."))
+            .collect()
+    });
+
+    // Merge the per-thread summaries into the final one. The merge only ever takes a lower
+    // minimum, a higher maximum, or adds sums/counts together, so the result is independent
+    // of how many threads were used or in which order their results are folded in.
+    let mut data_summary = StationTable::new();
+    let mut line_counter: u64 = 0;
+    for (chunk_summary, chunk_line_count) in chunk_results {
+        line_counter += chunk_line_count;
+        for city_data in chunk_summary {
+            merge_city_data(&mut data_summary, city_data);
+        }
+    }
+
+    (build_summary_string(&data_summary), line_counter)
+}
+
+/// Render `data_summary` into the final `{Station=min/mean/max, ...}` string, sorted
+/// alphabetically by station name (by raw UTF-8 bytes) to match the reference 1BRC
+/// output format.
+fn build_summary_string(data_summary: &StationTable) -> String {
+    let mut entries: Vec<&CityData> = data_summary.values().collect();
+    entries.sort_by(|a, b| a.city_name.cmp(b.city_name));
+
+    // a single allocation for the whole program
+    let mut summary_string = String::with_capacity(1024 * 1024 * 1024);
+    summary_string.push('{');
+    for city_data in entries {
+        city_data.summary(&mut summary_string);
+        summary_string.push(',');
+        summary_string.push(' ');
+    }
+    if summary_string.len() > 1 {
+        summary_string.pop(); // remove the last space
+        summary_string.pop(); // remove the last comma
+    }
+    summary_string.push('}');
+    summary_string
+}
+
+/// Owns either a memory-mapped view of the input file or, when mmap isn't available on this
+/// platform/filesystem, a plain heap buffer read the old way. Either way it derefs to the raw
+/// bytes, so the rest of the program doesn't need to care which path was taken.
+enum FileBytes {
+    Mapped(Mmap),
+    Owned(Vec<u8>),
+}
+
+impl std::ops::Deref for FileBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            FileBytes::Mapped(mmap) => mmap,
+            FileBytes::Owned(bytes) => bytes,
+        }
+    }
+}
+
+/// Memory-map `path`, falling back to a regular read if mmap can't be set up (e.g. the file
+/// lives on a filesystem that doesn't support it).
+fn load_file_bytes(path: &Path) -> FileBytes {
+    let file = File::open(path).expect("Could not open file.");
+
+    // Safety: the file is not expected to be concurrently truncated or modified by another
+    // process while we hold the mapping; this matches the assumptions of every other 1BRC
+    // solution that mmaps its input.
+    match unsafe { Mmap::map(&file) } {
+        Ok(mmap) => FileBytes::Mapped(mmap),
+        Err(_) => FileBytes::Owned(fs::read(path).expect("Could not read file.")),
+    }
+}
+
+/// Split `data` into up to `num_chunks` roughly equal byte ranges, each one snapped forward
+/// to the next `\n` so no chunk ever splits a line in half.
+fn split_into_chunks(data: &[u8], num_chunks: usize) -> Vec<&[u8]> {
+    if num_chunks <= 1 || data.is_empty() {
+        return vec![data];
+    }
+
+    let mut boundaries = Vec::with_capacity(num_chunks + 1);
+    boundaries.push(0);
+    for chunk_index in 1..num_chunks {
+        let mut boundary = chunk_index * data.len() / num_chunks;
+        while boundary < data.len() && data[boundary] != b'\n' {
+            boundary += 1;
+        }
+        if boundary < data.len() {
+            boundary += 1; // step past the newline so the next chunk starts on a fresh line
+        }
+        boundaries.push(boundary);
+    }
+    boundaries.push(data.len());
+
+    boundaries
+        .windows(2)
+        .filter(|window| window[0] < window[1])
+        .map(|window| &data[window[0]..window[1]])
+        .collect()
+}
+
+/// Process a single chunk of the file on a worker thread, building its own local summary.
+/// Returns the local summary together with the number of lines it processed.
+fn process_chunk<'a>(city_name_buffer: &'a Bump, chunk: &'a [u8]) -> (StationTable<'a>, u64) {
+    let mut data_summary = StationTable::new();
+    let mut line_counter: u64 = 0;
+
+    // Walk the chunk line by line using the SIMD scanner to jump straight to each `\n`,
+    // instead of a byte-by-byte loop.
+    let mut position = 0;
+    while position < chunk.len() {
+        let line_end = simd::find_byte(&chunk[position..], b'\n')
+            .map(|offset| position + offset)
+            .unwrap_or(chunk.len());
+        let line = &chunk[position..line_end];
+        if !line.is_empty() {
+            process_line(city_name_buffer, &mut data_summary, line);
+            line_counter += 1;
+        }
+        position = line_end + 1;
+    }
+
+    (data_summary, line_counter)
+}
+
+/// Fold `incoming` into `data_summary`, taking the lower minimum, the higher maximum, and
+/// adding the sums and counts together. This is associative, so the final result does not
+/// depend on the order in which per-thread summaries are merged.
+fn merge_city_data<'a>(data_summary: &mut StationTable<'a>, incoming: CityData<'a>) {
+    match data_summary.get_mut(incoming.city_name) {
+        Some(existing) => {
+            if incoming.minimum_temperature < existing.minimum_temperature {
+                existing.minimum_temperature = incoming.minimum_temperature;
+            }
+            if existing.maximum_temperature < incoming.maximum_temperature {
+                existing.maximum_temperature = incoming.maximum_temperature;
+            }
+            existing.temperatures_sum += incoming.temperatures_sum;
+            existing.data_points += incoming.data_points;
+        }
+        None => {
+            data_summary.insert(incoming.city_name, incoming);
+        }
     }
 }
 
@@ -117,11 +241,13 @@ impl<'a> CityData<'a> {
 /// This function is optimized to avoid heap allocation.
 fn process_line<'a: 'b, 'b>(
     city_name_buffer: &'a Bump,
-    data_summary: &'_ mut HashMap<&'b str, CityData<'b>>,
-    line: &'_ str,
+    data_summary: &'_ mut StationTable<'b>,
+    line: &'_ [u8],
 ) {
     // split the line into city and temperature
-    let (city_name, raw_temperature) = line.split_once(';').expect("Invalid line format.");
+    let separator_index = simd::find_byte(line, b';').expect("Invalid line format.");
+    let city_name = &line[..separator_index];
+    let raw_temperature = &line[separator_index + 1..];
 
     // convert the temperature to an integer
     let temperature = temperature_to_int(raw_temperature);
@@ -130,7 +256,7 @@ fn process_line<'a: 'b, 'b>(
     let city_data = match data_summary.get_mut(city_name) {
         Some(city_data) => city_data,
         None => {
-            let longlived_city_name: &str = city_name_buffer.alloc_str(city_name);
+            let longlived_city_name: &[u8] = city_name_buffer.alloc_slice_copy(city_name);
             data_summary.insert(
                 longlived_city_name,
                 CityData {
@@ -160,7 +286,7 @@ fn process_line<'a: 'b, 'b>(
 
 /// Convert a raw temperature string to an integer quickly.
 /// We take advantage of the fact that the temperature is given in 0.1° increments.
-fn temperature_to_int(raw_temperature: &str) -> i64 {
+fn temperature_to_int(raw_temperature: &[u8]) -> i64 {
     let mut temperature = 0;
     let mut is_negative = false;
 
@@ -168,15 +294,15 @@ fn temperature_to_int(raw_temperature: &str) -> i64 {
         panic!("Empty temperature string.")
     }
 
-    for c in raw_temperature.chars() {
-        match c {
-            '0'..='9' => {
+    for &byte in raw_temperature {
+        match byte {
+            b'0'..=b'9' => {
                 temperature *= 10;
-                temperature += (c as u8 - b'0') as i64;
+                temperature += (byte - b'0') as i64;
             }
-            '-' => is_negative = true,
-            '.' | '\n' => continue,
-            _ => unreachable!("Invalid character in temperature : {}.", c),
+            b'-' => is_negative = true,
+            b'.' | b'\r' => continue,
+            _ => unreachable!("Invalid character in temperature : {}.", byte as char),
         }
     }
 
@@ -188,34 +314,145 @@ fn temperature_to_int(raw_temperature: &str) -> i64 {
 }
 
 /// Convert an integer to a temperature string quickly.
-/// /!\ The temperature must not be more than TEMP_BUFF_SIZE digits long.
-fn int_to_temperature<const TEMP_BUFF_SIZE: usize>(
-    summary_string: &mut String,
-    mut temperature: i64,
-) {
-    let mut digits = [0u8; TEMP_BUFF_SIZE]; // this happens on the stack
-
-    if temperature == 0 {
-        summary_string.push_str("0.0");
-        return;
-    }
-
+/// `temperature` is in tenths of a degree, so its last digit is always the fractional
+/// digit; everything before it is the whole-number part. We used to carve a fixed-size
+/// stack buffer and insert the decimal point at a hardcoded position within it, which
+/// silently dropped the point (and the leading zero) for temperatures whose whole-number
+/// part is zero, so 0.5 came out as "5". Instead
+/// we split off the fractional digit up front and print the whole-number part on its own,
+/// so the decimal point always lands in the right place regardless of magnitude.
+fn int_to_temperature(summary_string: &mut String, mut temperature: i64) {
     if temperature < 0 {
         summary_string.push('-');
         temperature = -temperature;
     }
 
-    let mut index = TEMP_BUFF_SIZE - 1;
-    while temperature > 0 {
-        digits[index] = (temperature % 10) as u8;
-        index -= 1;
-        temperature /= 10;
+    let fractional_digit = (temperature % 10) as u8;
+    let mut whole_part = temperature / 10;
+
+    if whole_part == 0 {
+        summary_string.push('0');
+    } else {
+        const MAX_WHOLE_DIGITS: usize = 19; // enough digits for any i64
+        let mut digits = [0u8; MAX_WHOLE_DIGITS]; // this happens on the stack
+        let mut index = MAX_WHOLE_DIGITS;
+        while whole_part > 0 {
+            index -= 1;
+            digits[index] = (whole_part % 10) as u8;
+            whole_part /= 10;
+        }
+        for &digit in &digits[index..] {
+            summary_string.push((b'0' + digit) as char);
+        }
     }
 
-    for digit_index in index + 1..TEMP_BUFF_SIZE {
-        summary_string.push((b'0' + digits[digit_index]) as char);
-        if digit_index == 8 {
-            summary_string.push('.');
+    summary_string.push('.');
+    summary_string.push((b'0' + fractional_digit) as char);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_is_independent_of_chunk_count() {
+        // Includes a repeated station (to exercise the merge path) and a multi-byte UTF-8
+        // station name (to make sure a chunk boundary landing right after it, depending on
+        // thread count, never splits the name itself - chunk boundaries only ever land on
+        // `\n`, which can't appear inside a valid UTF-8 multi-byte sequence).
+        let data = "Abha;5.0\n\
+                    Abéché;12.3\n\
+                    Zurich;10.0\n\
+                    Abéché;-4.4\n\
+                    Monaco;7.5\n\
+                    Abha;-3.0\n\
+                    Zurich;2.0\n"
+            .as_bytes();
+
+        let (single_threaded, single_threaded_lines) = summarize(data, 1);
+        let (multi_threaded, multi_threaded_lines) = summarize(data, 4);
+
+        assert_eq!(single_threaded_lines, 7);
+        assert_eq!(single_threaded_lines, multi_threaded_lines);
+        assert_eq!(single_threaded, multi_threaded);
+    }
+
+    #[test]
+    fn summary_of_empty_table_is_an_empty_object() {
+        let data_summary = StationTable::new();
+        assert_eq!(build_summary_string(&data_summary), "{}");
+    }
+
+    #[test]
+    fn summary_is_sorted_alphabetically_by_station_name() {
+        let mut data_summary = StationTable::new();
+        for (city_name, temperature) in [
+            (b"Zurich".as_slice(), 100),
+            (b"Abha".as_slice(), 50),
+            (b"Monaco".as_slice(), 75),
+        ] {
+            data_summary.insert(
+                city_name,
+                CityData {
+                    city_name,
+                    minimum_temperature: temperature,
+                    maximum_temperature: temperature,
+                    temperatures_sum: temperature,
+                    data_points: 1,
+                },
+            );
         }
+
+        let summary = build_summary_string(&data_summary);
+
+        assert_eq!(
+            summary,
+            "{Abha=5.0/5.0/5.0, Monaco=7.5/7.5/7.5, Zurich=10.0/10.0/10.0}"
+        );
+    }
+
+    #[test]
+    fn mean_rounds_half_up_away_from_zero() {
+        let mut data_summary = StationTable::new();
+        // 45 / 2 = 22.5 tenths-of-a-degree, which should round up to 2.3, not truncate to 2.2.
+        data_summary.insert(
+            b"Hamburg".as_slice(),
+            CityData {
+                city_name: b"Hamburg".as_slice(),
+                minimum_temperature: -100,
+                maximum_temperature: 200,
+                temperatures_sum: 45,
+                data_points: 2,
+            },
+        );
+        // The symmetric negative case should round down to -2.3, not truncate to -2.2.
+        data_summary.insert(
+            b"Oslo".as_slice(),
+            CityData {
+                city_name: b"Oslo".as_slice(),
+                minimum_temperature: -200,
+                maximum_temperature: 100,
+                temperatures_sum: -45,
+                data_points: 2,
+            },
+        );
+
+        let summary = build_summary_string(&data_summary);
+
+        assert_eq!(
+            summary,
+            "{Hamburg=-10.0/2.3/20.0, Oslo=-20.0/-2.3/10.0}"
+        );
+    }
+
+    #[test]
+    fn single_digit_temperatures_still_get_a_decimal_point() {
+        let mut summary_string = String::new();
+        int_to_temperature(&mut summary_string, 5);
+        assert_eq!(summary_string, "0.5");
+
+        let mut summary_string = String::new();
+        int_to_temperature(&mut summary_string, -5);
+        assert_eq!(summary_string, "-0.5");
     }
 }
@@ -0,0 +1,191 @@
+//! SIMD-accelerated byte scanning, used to locate the `;` and `\n` delimiters while
+//! splitting a chunk into lines and a line into its station/temperature fields, instead
+//! of walking one byte at a time.
+
+const LANES: usize = 16;
+
+/// Find the first occurrence of `target` in `haystack`, scanning a vector's worth of
+/// bytes at a time on targets that support it, and falling back to a scalar byte loop
+/// everywhere else (including the final sub-vector tail on every target).
+pub(crate) fn find_byte(haystack: &[u8], target: u8) -> Option<usize> {
+    #[cfg(target_arch = "x86_64")]
+    {
+        find_byte_sse2(haystack, target)
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        find_byte_neon(haystack, target)
+    }
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+        find_byte_scalar(haystack, target)
+    }
+}
+
+fn find_byte_scalar(haystack: &[u8], target: u8) -> Option<usize> {
+    haystack.iter().position(|&byte| byte == target)
+}
+
+#[cfg(target_arch = "x86_64")]
+fn find_byte_sse2(haystack: &[u8], target: u8) -> Option<usize> {
+    use std::arch::x86_64::*;
+
+    let mut offset = 0;
+
+    // SSE2 is part of the x86_64 baseline, so it's always available here - no runtime
+    // feature check needed, unlike AVX2.
+    unsafe {
+        let target_vector = _mm_set1_epi8(target as i8);
+        while offset + LANES <= haystack.len() {
+            let chunk = _mm_loadu_si128(haystack.as_ptr().add(offset) as *const __m128i);
+            let matches = _mm_cmpeq_epi8(chunk, target_vector);
+            let mask = _mm_movemask_epi8(matches) as u32;
+            if mask != 0 {
+                return Some(offset + mask.trailing_zeros() as usize);
+            }
+            offset += LANES;
+        }
+    }
+
+    // Scalar tail handler for the bytes that didn't fill a full vector.
+    find_byte_scalar(&haystack[offset..], target).map(|index| offset + index)
+}
+
+#[cfg(all(test, target_arch = "x86_64"))]
+mod sse2_tests {
+    use super::*;
+
+    #[test]
+    fn finds_byte_in_haystack_shorter_than_a_lane() {
+        assert!(b"abc;def".len() < LANES);
+        assert_eq!(find_byte_sse2(b"abc;def", b';'), Some(3));
+        assert_eq!(find_byte_sse2(b"abcdef", b';'), None);
+    }
+
+    #[test]
+    fn finds_byte_in_haystack_exactly_one_lane_long() {
+        let haystack = b"aaaaaaaaaaaaaaa;"; // 16 bytes, match in the last byte of the vector
+        assert_eq!(haystack.len(), LANES);
+        assert_eq!(find_byte_sse2(haystack, b';'), Some(LANES - 1));
+    }
+
+    #[test]
+    fn finds_byte_only_in_the_scalar_tail() {
+        // A full, match-free 16-byte vector followed by a 4-byte scalar tail that holds
+        // the only occurrence of the target.
+        let haystack = b"aaaaaaaaaaaaaaaa;bcd";
+        assert_eq!(haystack.len(), LANES + 4);
+        assert_eq!(find_byte_sse2(haystack, b';'), Some(LANES));
+    }
+
+    #[test]
+    fn returns_none_when_the_byte_is_absent() {
+        let haystack = [b'a'; LANES * 2 + 3];
+        assert_eq!(find_byte_sse2(&haystack, b';'), None);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+fn find_byte_neon(haystack: &[u8], target: u8) -> Option<usize> {
+    use std::arch::aarch64::*;
+
+    let mut offset = 0;
+
+    // NEON is part of the aarch64 baseline, so it's always available here too.
+    unsafe {
+        let target_vector = vdupq_n_u8(target);
+        while offset + LANES <= haystack.len() {
+            let chunk = vld1q_u8(haystack.as_ptr().add(offset));
+            let matches = vceqq_u8(chunk, target_vector);
+            if vmaxvq_u8(matches) != 0 {
+                // NEON has no movemask instruction. Once we know this 16-byte window
+                // contains a match, a scalar scan over just those 16 bytes is cheap
+                // and avoids the bit-packing dance needed to extract an exact lane.
+                let window = std::slice::from_raw_parts(haystack.as_ptr().add(offset), LANES);
+                return find_byte_scalar(window, target).map(|index| offset + index);
+            }
+            offset += LANES;
+        }
+    }
+
+    // Scalar tail handler for the bytes that didn't fill a full vector.
+    find_byte_scalar(&haystack[offset..], target).map(|index| offset + index)
+}
+
+#[cfg(all(test, target_arch = "aarch64"))]
+mod neon_tests {
+    use super::*;
+
+    #[test]
+    fn finds_byte_in_haystack_shorter_than_a_lane() {
+        assert!(b"abc;def".len() < LANES);
+        assert_eq!(find_byte_neon(b"abc;def", b';'), Some(3));
+        assert_eq!(find_byte_neon(b"abcdef", b';'), None);
+    }
+
+    #[test]
+    fn finds_byte_in_haystack_exactly_one_lane_long() {
+        let haystack = b"aaaaaaaaaaaaaaa;"; // 16 bytes, match in the last byte of the vector
+        assert_eq!(haystack.len(), LANES);
+        assert_eq!(find_byte_neon(haystack, b';'), Some(LANES - 1));
+    }
+
+    #[test]
+    fn finds_byte_only_in_the_scalar_tail() {
+        // A full, match-free 16-byte vector followed by a 4-byte scalar tail that holds
+        // the only occurrence of the target.
+        let haystack = b"aaaaaaaaaaaaaaaa;bcd";
+        assert_eq!(haystack.len(), LANES + 4);
+        assert_eq!(find_byte_neon(haystack, b';'), Some(LANES));
+    }
+
+    #[test]
+    fn returns_none_when_the_byte_is_absent() {
+        let haystack = [b'a'; LANES * 2 + 3];
+        assert_eq!(find_byte_neon(&haystack, b';'), None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // These exercise the public `find_byte` entry point directly, so they cover whichever
+    // of the SIMD/scalar implementations this target actually dispatches to.
+
+    #[test]
+    fn finds_byte_in_haystack_shorter_than_a_lane() {
+        assert!(b"abc;def".len() < LANES);
+        assert_eq!(find_byte(b"abc;def", b';'), Some(3));
+        assert_eq!(find_byte(b"abcdef", b';'), None);
+    }
+
+    #[test]
+    fn finds_byte_in_haystack_exactly_one_lane_long() {
+        let haystack = b"aaaaaaaaaaaaaaa;"; // 16 bytes, match in the last byte of the vector
+        assert_eq!(haystack.len(), LANES);
+        assert_eq!(find_byte(haystack, b';'), Some(LANES - 1));
+    }
+
+    #[test]
+    fn finds_byte_only_in_the_scalar_tail() {
+        let haystack = b"aaaaaaaaaaaaaaaa;bcd";
+        assert_eq!(haystack.len(), LANES + 4);
+        assert_eq!(find_byte(haystack, b';'), Some(LANES));
+    }
+
+    #[test]
+    fn returns_none_when_the_byte_is_absent() {
+        let haystack = [b'a'; LANES * 2 + 3];
+        assert_eq!(find_byte(&haystack, b';'), None);
+    }
+
+    #[test]
+    fn finds_byte_at_the_first_and_last_positions() {
+        assert_eq!(find_byte(b";aaaaaaaaaaaaaaa", b';'), Some(0));
+        let mut haystack = vec![b'a'; LANES * 2];
+        let last_index = haystack.len() - 1;
+        haystack[last_index] = b';';
+        assert_eq!(find_byte(&haystack, b';'), Some(last_index));
+    }
+}
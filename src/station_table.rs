@@ -0,0 +1,214 @@
+//! A purpose-built hash table for the station -> temperature-stats summary.
+//!
+//! `std::collections::HashMap` defaults to SipHash, which is built to resist
+//! hash-flooding attacks on untrusted input. Station names are short, known to be
+//! attacker-free, and looked up millions of times per run, so that resistance is pure
+//! overhead here. This table trades it for a cheap FNV-style hash and open addressing
+//! with linear probing, and exposes the `get_mut`/`insert` surface `process_line`
+//! already relies on, plus iteration over the entries for merging and output.
+
+/// Data structure to hold a single city data
+/// Instead of using floats, we use integers to represent the temperature,
+/// as we know that the temperature is given in 0.1° increments
+#[derive(Clone, Copy)]
+pub(crate) struct CityData<'a> {
+    pub(crate) city_name: &'a [u8],
+    pub(crate) minimum_temperature: i64,
+    pub(crate) maximum_temperature: i64,
+    pub(crate) temperatures_sum: i64,
+    pub(crate) data_points: i64,
+}
+
+impl<'a> CityData<'a> {
+    /// Implement a summary function for the CityData struct
+    /// We don't want heap allocation, so we use a mutable string reference on which we append the summary.
+    pub(crate) fn summary(&self, summary_string: &mut String) {
+        let city_name =
+            std::str::from_utf8(self.city_name).expect("Station name was not valid UTF-8.");
+        summary_string.push_str(city_name);
+        summary_string.push('=');
+        crate::int_to_temperature(summary_string, self.minimum_temperature);
+        summary_string.push('/');
+        crate::int_to_temperature(
+            summary_string,
+            round_half_up_div(self.temperatures_sum, self.data_points),
+        );
+        summary_string.push('/');
+        crate::int_to_temperature(summary_string, self.maximum_temperature);
+    }
+}
+
+/// Divide `numerator` by `denominator` (always positive, a count of data points),
+/// rounding half away from zero to match the official challenge's rounding rule -
+/// e.g. a mean of 2.25 rounds to 2.3, and -2.25 rounds to -2.3.
+fn round_half_up_div(numerator: i64, denominator: i64) -> i64 {
+    if numerator >= 0 {
+        (2 * numerator + denominator) / (2 * denominator)
+    } else {
+        (2 * numerator - denominator) / (2 * denominator)
+    }
+}
+
+/// The table starts at this many slots. The challenge guarantees a bounded station set
+/// (~10k distinct names), so at a 70% max load factor a single table this size almost
+/// never needs to grow.
+const INITIAL_CAPACITY: usize = 1 << 16;
+/// Grow and rehash once the table gets this full, to keep probe sequences short.
+const MAX_LOAD_FACTOR: f64 = 0.7;
+
+/// An open-addressing hash table keyed on a station's raw byte slice, with entries
+/// stored inline (no boxing) and collisions resolved by linear probing.
+pub(crate) struct StationTable<'a> {
+    slots: Vec<Option<CityData<'a>>>,
+    len: usize,
+}
+
+impl<'a> StationTable<'a> {
+    pub(crate) fn new() -> Self {
+        Self::with_capacity(INITIAL_CAPACITY)
+    }
+
+    /// `capacity` must be a power of two. Only exposed to let tests force collisions and
+    /// grow/rehash with a tiny table instead of needing ~45k inserts to fill the real one.
+    fn with_capacity(capacity: usize) -> Self {
+        debug_assert!(capacity.is_power_of_two());
+        Self {
+            slots: vec![None; capacity],
+            len: 0,
+        }
+    }
+
+    /// Cheap multiplicative/FNV-style hash: fold each byte into a running hash with a
+    /// multiply and an xor. Good enough distribution for short station names, far
+    /// cheaper than SipHash.
+    fn hash(name: &[u8]) -> u64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for &byte in name {
+            hash = hash.wrapping_mul(0x100000001b3) ^ byte as u64;
+        }
+        hash
+    }
+
+    /// Probe for `name`, returning the slot index it occupies if present.
+    fn find_slot(&self, name: &[u8]) -> Option<usize> {
+        let mask = self.slots.len() - 1;
+        let mut index = Self::hash(name) as usize & mask;
+        loop {
+            match &self.slots[index] {
+                Some(entry) if entry.city_name == name => return Some(index),
+                Some(_) => index = (index + 1) & mask,
+                None => return None,
+            }
+        }
+    }
+
+    pub(crate) fn get_mut(&mut self, name: &[u8]) -> Option<&mut CityData<'a>> {
+        let index = self.find_slot(name)?;
+        self.slots[index].as_mut()
+    }
+
+    pub(crate) fn insert(&mut self, name: &'a [u8], data: CityData<'a>) {
+        if (self.len + 1) as f64 > self.slots.len() as f64 * MAX_LOAD_FACTOR {
+            self.grow();
+        }
+        self.insert_into_slots(name, data);
+    }
+
+    /// Place `data` into the first free slot of the probe sequence, without checking
+    /// the load factor. Used both by `insert` (after it has already grown if needed)
+    /// and by `grow` (which rehashes into an already correctly-sized table).
+    fn insert_into_slots(&mut self, name: &[u8], data: CityData<'a>) {
+        let mask = self.slots.len() - 1;
+        let mut index = Self::hash(name) as usize & mask;
+        while self.slots[index].is_some() {
+            index = (index + 1) & mask;
+        }
+        self.slots[index] = Some(data);
+        self.len += 1;
+    }
+
+    fn grow(&mut self) {
+        let new_capacity = self.slots.len() * 2;
+        let old_slots = std::mem::replace(&mut self.slots, vec![None; new_capacity]);
+        self.len = 0;
+        for entry in old_slots.into_iter().flatten() {
+            self.insert_into_slots(entry.city_name, entry);
+        }
+    }
+
+    pub(crate) fn values(&self) -> impl Iterator<Item = &CityData<'a>> {
+        self.slots.iter().filter_map(|slot| slot.as_ref())
+    }
+}
+
+impl<'a> IntoIterator for StationTable<'a> {
+    type Item = CityData<'a>;
+    type IntoIter = std::iter::Flatten<std::vec::IntoIter<Option<CityData<'a>>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.slots.into_iter().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(city_name: &[u8], temperature: i64) -> CityData<'_> {
+        CityData {
+            city_name,
+            minimum_temperature: temperature,
+            maximum_temperature: temperature,
+            temperatures_sum: temperature,
+            data_points: 1,
+        }
+    }
+
+    #[test]
+    fn collisions_are_resolved_by_linear_probing() {
+        // Confirm these two names actually hash to the same slot at capacity 4 before
+        // relying on that fact - otherwise this test would silently cover nothing. Capacity
+        // 4 (rather than 2) leaves empty slots behind after both inserts, so a lookup miss
+        // still terminates by hitting a `None` slot instead of probing forever.
+        assert_eq!(StationTable::hash(b"Oslo") & 3, 0);
+        assert_eq!(StationTable::hash(b"Monaco") & 3, 0);
+
+        // Insert directly through `insert_into_slots` rather than the public `insert`, so
+        // no load-factor check can grow the table and mask the collision (two entries stay
+        // under capacity 4's 70% threshold of 2.8, but this keeps the test independent of it).
+        let mut table = StationTable::with_capacity(4);
+        table.insert_into_slots(b"Oslo", entry(b"Oslo", 10));
+        table.insert_into_slots(b"Monaco", entry(b"Monaco", 20));
+
+        assert_eq!(table.get_mut(b"Oslo").unwrap().minimum_temperature, 10);
+        assert_eq!(table.get_mut(b"Monaco").unwrap().minimum_temperature, 20);
+        assert!(table.get_mut(b"Zurich").is_none());
+    }
+
+    #[test]
+    fn grow_rehashes_without_losing_or_corrupting_entries() {
+        // Starting from a capacity of 2 and a 70% max load factor, inserting six entries
+        // forces the table through several grow/rehash cycles (2 -> 4 -> 8 -> 16).
+        let mut table = StationTable::with_capacity(2);
+        let names: [&[u8]; 6] = [b"Abha", b"Oslo", b"Monaco", b"Zurich", b"Hamburg", b"Paris"];
+        for (index, &name) in names.iter().enumerate() {
+            table.insert(name, entry(name, index as i64 * 10));
+        }
+
+        for (index, &name) in names.iter().enumerate() {
+            let expected_temperature = index as i64 * 10;
+            let city_data = table
+                .get_mut(name)
+                .expect("entry should survive growth/rehash");
+            assert_eq!(city_data.minimum_temperature, expected_temperature);
+        }
+    }
+
+    #[test]
+    fn get_mut_returns_none_for_a_name_that_was_never_inserted() {
+        let mut table = StationTable::new();
+        table.insert(b"Abha", entry(b"Abha", 10));
+
+        assert!(table.get_mut(b"Oslo").is_none());
+    }
+}